@@ -6,14 +6,17 @@ use quicli::prelude::*;
 use structopt::StructOpt;
 use signal_hook::{iterator::Signals, SIGINT};
 
-use rtlsdr::{Device, get_devices};
+use rtlsdr::{Device, get_devices, Demodulator, NbfmDemod, ReadAsyncConfig};
 
 const INFINITE_SAMPLES : usize = 0;
 const AUTO_GAIN : i32 = -100;
+const MAX_GAIN : i32 = -200;
 
 fn parse_auto_int(src: &str) -> Result<i32, ParseIntError> {
     if src == "auto" {
         Ok(AUTO_GAIN)
+    } else if src == "max" {
+        Ok(MAX_GAIN)
     } else {
         i32::from_str_radix(src, 10)
     }
@@ -41,7 +44,7 @@ struct Cli {
     #[structopt(long = "device-index", short = "d", default_value = "0")]
     device_index: u32,
 
-    /// Gain (0 for auto)
+    /// Gain (auto for the tuner's automatic gain mode, max for the tuner's highest supported gain, or a tenths-of-dB value snapped to the nearest supported step)
     #[structopt(long = "gain", short = "g", default_value = "auto", parse(try_from_str = "parse_auto_int"))]
     gain: i32,
 
@@ -52,14 +55,41 @@ struct Cli {
     /// Output block size
     #[structopt(long = "output-block-size", short = "b", default_value = "262144")]
     output_block_size: u32,
-    
+
+    /// Number of USB transfer buffers to use for async reads (0 for the librtlsdr default)
+    #[structopt(long = "buf-num", default_value = "0")]
+    buf_num: u32,
+
     /// Number of samples to read
     #[structopt(long = "samples", short = "n", default_value = "infinite", parse(try_from_str = "parse_infinite_int"))]
     samples: usize,
 
+    /// Demodulate the capture instead of writing raw IQ (supported: nbfm)
+    #[structopt(long = "demod")]
+    demod: Option<String>,
+
+    /// Output file for demodulated audio PCM, required when --demod is set
+    #[structopt(long = "output")]
+    output: Option<String>,
+
+    /// Target audio sample rate (Hz) for --demod, used to derive the decimation factor from --sample-rate
+    #[structopt(long = "audio-rate", default_value = "48000")]
+    audio_rate: u32,
 
-    /// filename for output
-    file: String,
+    /// Decimation factor from IQ sample rate down to audio rate, used by --demod (overrides --audio-rate)
+    #[structopt(long = "decimation")]
+    decimation: Option<u32>,
+
+    /// Disable the DC-blocking high-pass filter on demodulated audio
+    #[structopt(long = "no-dc-block")]
+    no_dc_block: bool,
+
+    /// Disable the tuner's internal frequency dithering (useful for coherent multi-dongle receivers)
+    #[structopt(long = "disable-dithering")]
+    disable_dithering: bool,
+
+    /// filename for raw IQ output; required unless --demod is set
+    file: Option<String>,
 
     #[structopt(flatten)]
     verbosity: Verbosity,
@@ -110,30 +140,65 @@ fn main() -> CliResult {
     /* Set gain, frequency, sample rate, and reset the device. */
     if args.gain == AUTO_GAIN {
         device.set_tuner_gain_mode(0)?;
+    } else if args.gain == MAX_GAIN {
+        device.set_tuner_gain_max()?;
     } else {
-        device.set_tuner_gain_mode(1)?;
-        device.set_tuner_gain(args.gain)?;
+        device.set_tuner_gain_nearest(args.gain)?;
     }
     if args.ppm_error != 0 {
         device.set_freq_correction(args.ppm_error)?;
     }
+    if args.disable_dithering {
+        device.set_dithering(false)?;
+    }
     device.set_center_freq(args.frequency)?;
     device.set_sample_rate(args.sample_rate)?;
     device.reset_buffer()?;
 
     info!("Gain reported by device: {}", f64::from(device.get_tuner_gain())/10.0);
 
-    let mut file = File::create(args.file.clone())?;
+    let decimation = args.decimation.unwrap_or_else(|| (args.sample_rate / args.audio_rate).max(1));
+    let mut demod: Option<Box<dyn Demodulator>> = match args.demod.as_deref() {
+        None => None,
+        Some("nbfm") => Some(Box::new(NbfmDemod::new(decimation, !args.no_dc_block))),
+        Some(other) => Err(TextError::new(format!("Unsupported demodulation mode: {}", other)))?,
+    };
+    let mut output_file = match (&demod, &args.output) {
+        (Some(_), Some(path)) => Some(File::create(path)?),
+        (Some(_), None) => Err(TextError::new("--output is required when --demod is set.".to_string()))?,
+        (None, _) => None,
+    };
+
+    let mut file = match (&demod, &args.file) {
+        (None, Some(path)) => Some(File::create(path)?),
+        (None, None) => Err(TextError::new("a file argument is required when --demod is not set.".to_string()))?,
+        (Some(_), _) => None,
+    };
     info!("Reading data");
     setup_signal_handler(device.clone())?;
 
     let mut closure_device = device.clone();
     let mut total_bytes_written = 0;
-    device.read_async(args.output_block_size, move |data| {
-        let bytes_written = file.write(data).unwrap();
+    let read_async_config = ReadAsyncConfig { buf_num: args.buf_num, output_block_size: args.output_block_size };
+    device.read_async_with_config(read_async_config, move |data| {
+        let (bytes_written, short_write) = match demod.as_mut() {
+            Some(demod) => {
+                let pcm = demod.process(data);
+                let mut pcm_bytes = Vec::with_capacity(pcm.len() * 2);
+                for sample in pcm {
+                    pcm_bytes.extend_from_slice(&sample.to_le_bytes());
+                }
+                let written = output_file.as_mut().unwrap().write(&pcm_bytes).unwrap();
+                (data.len(), written != pcm_bytes.len())
+            }
+            None => {
+                let written = file.as_mut().unwrap().write(data).unwrap();
+                (written, written != data.len())
+            }
+        };
         total_bytes_written += bytes_written;
         trace!("Wrote {} bytes of data ({} total)", bytes_written, total_bytes_written);
-        if bytes_written != data.len() {
+        if short_write {
             trace!("Wrote fewer bytes than was available. Cancelling.");
             closure_device.cancel_async().unwrap();
         }
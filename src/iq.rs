@@ -0,0 +1,39 @@
+use num_complex::Complex32;
+
+/// Converts a block of raw 8-bit unsigned interleaved I/Q samples, as read
+/// from [`Device::read_sync`](crate::Device::read_sync) or
+/// [`Device::read_async`](crate::Device::read_async), into normalized
+/// complex samples centered on zero.
+///
+/// `data` is expected to have an even length (I, Q, I, Q, ...); a trailing
+/// unpaired byte is ignored.
+pub fn iq_to_complex(data: &[u8]) -> Vec<Complex32> {
+    data.chunks_exact(2)
+        .map(|pair| {
+            let i = (pair[0] as f32 - 127.5) / 127.5;
+            let q = (pair[1] as f32 - 127.5) / 127.5;
+            Complex32::new(i, q)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_extremes_and_midpoints() {
+        let samples = iq_to_complex(&[0, 255, 127, 128]);
+        assert_eq!(samples.len(), 2);
+        assert!((samples[0].re - -1.0).abs() < 1e-6);
+        assert!((samples[0].im - 1.0).abs() < 1e-6);
+        assert!((samples[1].re - (-0.5 / 127.5)).abs() < 1e-6);
+        assert!((samples[1].im - (0.5 / 127.5)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn ignores_trailing_unpaired_byte() {
+        let samples = iq_to_complex(&[0, 255, 127]);
+        assert_eq!(samples.len(), 1);
+    }
+}
@@ -4,6 +4,12 @@ use librtlsdr_sys::*;
 use std::ffi::CStr;
 use std::io::Error;
 
+mod iq;
+pub use iq::iq_to_complex;
+
+mod demod;
+pub use demod::{Demodulator, NbfmDemod};
+
 #[derive(Debug, Clone)]
 pub struct Device {
     device: *mut rtlsdr_dev_t,
@@ -44,6 +50,70 @@ impl Device {
         Ok(())
     }
 
+    pub fn get_tuner_gains(&mut self) -> Result<Vec<i32>, Error> {
+        let count = unsafe { rtlsdr_get_tuner_gains(self.device, 0 as *mut i32) };
+        if count < 0 {
+            return Err(Error::last_os_error());
+        }
+        let mut gains: Vec<i32> = vec![0; count as usize];
+        let err_val = unsafe { rtlsdr_get_tuner_gains(self.device, gains.as_mut_ptr()) };
+        if err_val < 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok(gains)
+    }
+
+    pub fn set_tuner_gain_max(&mut self) -> Result<(), Error> {
+        let gains = self.get_tuner_gains()?;
+        match gains.into_iter().max() {
+            Some(gain) => {
+                info!("Setting tuner gain to max supported value: {}", gain);
+                self.set_tuner_gain_mode(1)?;
+                self.set_tuner_gain(gain)
+            }
+            None => Err(Error::new(std::io::ErrorKind::InvalidInput, "device reports no supported tuner gains")),
+        }
+    }
+
+    pub fn set_tuner_gain_nearest(&mut self, target: i32) -> Result<(), Error> {
+        let gains = self.get_tuner_gains()?;
+        let nearest = gains.into_iter().min_by_key(|gain| (target - gain).abs());
+        match nearest {
+            Some(gain) => {
+                info!("Setting tuner gain to nearest supported value for {}: {}", target, gain);
+                self.set_tuner_gain_mode(1)?;
+                self.set_tuner_gain(gain)
+            }
+            None => Err(Error::new(std::io::ErrorKind::InvalidInput, "device reports no supported tuner gains")),
+        }
+    }
+
+    pub fn set_tuner_gain_by_index(&mut self, index: usize) -> Result<(), Error> {
+        let gains = self.get_tuner_gains()?;
+        match gains.get(index) {
+            Some(&gain) => {
+                info!("Setting tuner gain to index {}: {}", index, gain);
+                self.set_tuner_gain_mode(1)?;
+                self.set_tuner_gain(gain)
+            }
+            None => Err(Error::new(std::io::ErrorKind::InvalidInput, "tuner gain index out of range")),
+        }
+    }
+
+    pub fn set_tuner_gain_by_percent(&mut self, pct: u32) -> Result<(), Error> {
+        let pct = pct.min(100);
+        let mut gains = self.get_tuner_gains()?;
+        if gains.is_empty() {
+            return Err(Error::new(std::io::ErrorKind::InvalidInput, "device reports no supported tuner gains"));
+        }
+        gains.sort();
+        let index = ((gains.len() - 1) * pct as usize) / 100;
+        let gain = gains[index];
+        info!("Setting tuner gain to {}% of the supported range: {}", pct, gain);
+        self.set_tuner_gain_mode(1)?;
+        self.set_tuner_gain(gain)
+    }
+
     pub fn set_freq_correction(&mut self, ppm_error: i32) -> Result<(), Error> {
         info!("Setting frequency correction to {}", ppm_error);
         let err_val = unsafe { rtlsdr_set_freq_correction(self.device, ppm_error) };
@@ -71,6 +141,15 @@ impl Device {
         Ok(())
     }
 
+    pub fn set_dithering(&mut self, enable: bool) -> Result<(), Error> {
+        info!("Setting tuner dithering to {}", enable);
+        let err_val = unsafe { rtlsdr_set_dithering(self.device, enable as i32) };
+        if err_val != 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok(())
+    }
+
     pub fn reset_buffer(&mut self) -> Result<(), Error> {
         debug!("Resetting buffer");
         let err_val = unsafe { rtlsdr_reset_buffer(self.device) };
@@ -80,11 +159,25 @@ impl Device {
         Ok(())
     }
 
+    pub fn read_sync(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let mut n_read: i32 = 0;
+        let err_val = unsafe { rtlsdr_read_sync(self.device, buf.as_mut_ptr() as *mut std::ffi::c_void, buf.len() as i32, &mut n_read as *mut i32) };
+        if err_val != 0 {
+            return Err(Error::last_os_error());
+        }
+        Ok(n_read as usize)
+    }
+
     pub fn read_async<CB>(&mut self, output_block_size: u32, callback: CB) -> Result<(), Error>
+    where CB: FnMut(&[u8]) + 'static {
+        self.read_async_with_config(ReadAsyncConfig { buf_num: 0, output_block_size }, callback)
+    }
+
+    pub fn read_async_with_config<CB>(&mut self, config: ReadAsyncConfig, callback: CB) -> Result<(), Error>
     where CB: FnMut(&[u8]) + 'static {
         let boxed_callback = Box::new(callback);
         let tmp: Box<Box<AsyncClosureReader>> = Box::new(Box::new(AsyncClosureReader::new(boxed_callback)));
-        let err_val = unsafe { rtlsdr_read_async(self.device, Some(async_callback), Box::into_raw(tmp) as *mut Box<AsyncClosureReader> as *mut std::ffi::c_void, 0, output_block_size) };
+        let err_val = unsafe { rtlsdr_read_async(self.device, Some(async_callback), Box::into_raw(tmp) as *mut Box<AsyncClosureReader> as *mut std::ffi::c_void, config.buf_num, config.output_block_size) };
         if err_val != 0 {
             return Err(Error::last_os_error());
         }
@@ -115,6 +208,14 @@ impl Device {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct ReadAsyncConfig {
+    /// Number of USB transfer buffers to allocate. 0 lets librtlsdr pick its default (15).
+    pub buf_num: u32,
+    /// Size in bytes of each buffer passed to the callback.
+    pub output_block_size: u32,
+}
+
 pub struct AsyncClosureReader {
     callback: Box<FnMut(&[u8])>,
 }